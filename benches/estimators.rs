@@ -0,0 +1,43 @@
+use chrono::DateTime;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use real_vol::estimators::{calculate_parkinson, rolling_variance, Estimator, EstimatorOptions};
+use real_vol::providers::CandleData;
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+fn synthetic_candles(n: usize) -> Vec<CandleData> {
+    (0..n)
+        .map(|i| {
+            let close = 30_000.0 + 1_000.0 * (i as f64 / 10.0).sin();
+            CandleData {
+                start: DateTime::from_timestamp_millis(i as i64 * DAY_MILLIS).unwrap(),
+                open: close * 0.995,
+                high: close * 1.02,
+                low: close * 0.98,
+                close,
+                volume: "0".to_string(),
+                turnover: "0".to_string(),
+            }
+        })
+        .collect()
+}
+
+fn bench_estimators(c: &mut Criterion) {
+    let mut group = c.benchmark_group("estimators");
+    for n in [365, 10_000] {
+        let candles = synthetic_candles(n);
+        group.bench_with_input(BenchmarkId::new("parkinson", n), &candles, |b, candles| {
+            b.iter(|| calculate_parkinson(candles))
+        });
+        for estimator in Estimator::ALL {
+            let name = format!("rolling_{estimator:?}_30");
+            group.bench_with_input(BenchmarkId::new(name, n), &candles, |b, candles| {
+                b.iter(|| rolling_variance(estimator, candles, 30, &EstimatorOptions::default()))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_estimators);
+criterion_main!(benches);
@@ -0,0 +1,771 @@
+use std::f64;
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+
+use crate::format::decimal;
+use crate::providers::CandleData;
+
+fn parkinson_term(kline: &CandleData) -> f64 {
+    (kline.high.ln() - kline.low.ln()).powi(2)
+}
+
+fn parkinson_from_sum(sum: f64, len: usize) -> f64 {
+    sum / (4.0 * len as f64 * f64::consts::LN_2)
+}
+
+pub const DAYS_PER_YEAR: f64 = 365.25;
+
+/// Candles per year for a Bybit kline interval (`1`..`720` minutes, `D`, `W`,
+/// `M`). Crypto trades around the clock, so a day is 24 hours and a year is
+/// 365.25 days.
+pub fn periods_per_year(interval: &str) -> Option<f64> {
+    match interval {
+        "1" | "3" | "5" | "15" | "30" | "60" | "120" | "240" | "360" | "720" => {
+            let minutes: f64 = interval.parse().ok()?;
+            Some(DAYS_PER_YEAR * 24.0 * 60.0 / minutes)
+        }
+        "D" => Some(DAYS_PER_YEAR),
+        "W" => Some(DAYS_PER_YEAR / 7.0),
+        "M" => Some(12.0),
+        _ => None,
+    }
+}
+
+/// Candles per year implied by the median gap between consecutive `start`s,
+/// for data whose interval label can't be trusted. `None` with fewer than two
+/// distinct starts.
+pub fn inferred_periods_per_year(klines: &[CandleData]) -> Option<f64> {
+    let mut gaps: Vec<f64> = klines
+        .windows(2)
+        .map(|pair| (pair[1].start - pair[0].start).num_seconds().abs() as f64)
+        .filter(|&gap| gap > 0.0)
+        .collect();
+    if gaps.is_empty() {
+        return None;
+    }
+    gaps.sort_by(f64::total_cmp);
+    let median = quantile(&gaps, 0.5);
+    Some(DAYS_PER_YEAR * 24.0 * 60.0 * 60.0 / median)
+}
+
+/// Scales a per-period variance to an annualized volatility.
+pub fn annualize(variance: f64, periods_per_year: f64) -> f64 {
+    (variance * periods_per_year).sqrt()
+}
+
+/// Per-period Parkinson variance from high/low ranges. Works on candles of
+/// any interval; annualize with the `periods_per_year` of that interval.
+pub fn calculate_parkinson(klines: &[CandleData]) -> f64 {
+    let sum: f64 = klines.iter().map(parkinson_term).sum();
+    parkinson_from_sum(sum, klines.len())
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Contribution {
+    pub start: DateTime<Utc>,
+    /// The candle's squared log range, `ln(high / low)^2`.
+    #[serde(serialize_with = "decimal")]
+    pub term: f64,
+    /// Share of the window's Parkinson variance, in percent.
+    #[serde(serialize_with = "decimal")]
+    pub percent: f64,
+}
+
+/// Each candle's part in the Parkinson variance of `klines`, largest first.
+pub fn parkinson_contributions(klines: &[CandleData]) -> Vec<Contribution> {
+    let total: f64 = klines.iter().map(parkinson_term).sum();
+    let mut contributions: Vec<Contribution> = klines
+        .iter()
+        .map(|kline| {
+            let term = parkinson_term(kline);
+            Contribution {
+                start: kline.start,
+                term,
+                percent: 100.0 * term / total,
+            }
+        })
+        .collect();
+    contributions.sort_by(|a, b| b.term.total_cmp(&a.term));
+    contributions
+}
+
+/// Per-period Parkinson variance of `klines` and the variance of that
+/// estimate, from the empirical variance of the per-candle terms divided by
+/// the number of candles. `None` for fewer than two candles.
+pub fn parkinson_with_error(klines: &[CandleData]) -> Option<(f64, f64)> {
+    if klines.len() < 2 {
+        return None;
+    }
+    let scale = 4.0 * f64::consts::LN_2;
+    let terms: Vec<f64> = klines
+        .iter()
+        .map(|kline| parkinson_term(kline) / scale)
+        .collect();
+    let n = terms.len() as f64;
+    let variance = terms.iter().sum::<f64>() / n;
+    let term_variance = terms.iter().map(|t| (t - variance).powi(2)).sum::<f64>() / (n - 1.0);
+    Some((variance, term_variance / n))
+}
+
+/// Per-period Parkinson variance with each candle's squared range weighted by
+/// `weights` (e.g. the open interest backing it) instead of equally.
+pub fn weighted_parkinson(klines: &[CandleData], weights: &[f64]) -> f64 {
+    let total: f64 = weights.iter().sum();
+    let weighted: f64 = klines
+        .iter()
+        .zip(weights)
+        .map(|(kline, weight)| weight * parkinson_term(kline))
+        .sum();
+    weighted / (4.0 * total * f64::consts::LN_2)
+}
+
+/// Per-period Parkinson variance of every `window`-candle window, oldest first. The
+/// value at index `i` covers `klines[i..i + window]`. Uses a running sum, so
+/// this is O(n) regardless of window size.
+pub fn rolling_parkinson(klines: &[CandleData], window: usize) -> Vec<f64> {
+    if window == 0 || klines.len() < window {
+        return Vec::new();
+    }
+    let terms: Vec<f64> = klines.iter().map(parkinson_term).collect();
+    let mut sum: f64 = terms[..window].iter().sum();
+    let mut variances = Vec::with_capacity(terms.len() - window + 1);
+    variances.push(parkinson_from_sum(sum, window));
+    for i in window..terms.len() {
+        sum += terms[i] - terms[i - window];
+        // Guard against the running sum drifting just below zero.
+        variances.push(parkinson_from_sum(sum.max(0.0), window));
+    }
+    variances
+}
+
+/// Garman-Klass per-candle term. Uses open and close as well as the range,
+/// which makes it several times more efficient than Parkinson under
+/// continuous trading without drift.
+fn garman_klass_term(kline: &CandleData) -> f64 {
+    let range = (kline.high / kline.low).ln();
+    let body = (kline.close / kline.open).ln();
+    0.5 * range.powi(2) - (2.0 * f64::consts::LN_2 - 1.0) * body.powi(2)
+}
+
+/// Rogers-Satchell per-candle term, which stays unbiased when the price
+/// drifts within the candle.
+fn rogers_satchell_term(kline: &CandleData) -> f64 {
+    (kline.high / kline.close).ln() * (kline.high / kline.open).ln()
+        + (kline.low / kline.close).ln() * (kline.low / kline.open).ln()
+}
+
+/// Mean of every `window`-long run of `terms`, with a running sum.
+fn rolling_mean(terms: &[f64], window: usize) -> Vec<f64> {
+    if window == 0 || terms.len() < window {
+        return Vec::new();
+    }
+    let mut sum: f64 = terms[..window].iter().sum();
+    let mut means = Vec::with_capacity(terms.len() - window + 1);
+    means.push(sum / window as f64);
+    for i in window..terms.len() {
+        sum += terms[i] - terms[i - window];
+        means.push(sum / window as f64);
+    }
+    means
+}
+
+/// RiskMetrics decay for daily returns.
+pub const EWMA_LAMBDA: f64 = 0.94;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Estimator {
+    #[default]
+    Parkinson,
+    GarmanKlass,
+    RogersSatchell,
+    CloseToClose,
+    Ewma,
+}
+
+impl Estimator {
+    pub const ALL: [Estimator; 5] = [
+        Estimator::Parkinson,
+        Estimator::GarmanKlass,
+        Estimator::RogersSatchell,
+        Estimator::CloseToClose,
+        Estimator::Ewma,
+    ];
+}
+
+/// Parses the same snake_case names the query string uses, e.g. for
+/// environment variables.
+impl std::str::FromStr for Estimator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Estimator::deserialize(s.into_deserializer())
+            .map_err(|err: serde::de::value::Error| err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EstimatorOptions {
+    /// Divide by `n - 1` (sample) instead of `n` (population) in the
+    /// standard-deviation-based estimators. Ignored by Parkinson.
+    pub bessel: bool,
+    /// Clip log returns to the `[p, 1 - p]` quantiles of their window before
+    /// the return-based estimators run, so each point only depends on its own
+    /// window's returns. Range-based estimators
+    /// (Parkinson, Garman-Klass, Rogers-Satchell) never see returns and are
+    /// unaffected.
+    pub winsorize: Option<f64>,
+    /// Leave out this fraction of each window's returns, largest in absolute
+    /// value first, before close-to-close runs. What remains is "diffusive"
+    /// volatility that deliberately understates jump risk. Other estimators
+    /// ignore it.
+    pub drop_outliers: Option<f64>,
+}
+
+impl Default for EstimatorOptions {
+    fn default() -> Self {
+        EstimatorOptions {
+            bessel: true,
+            winsorize: None,
+            drop_outliers: None,
+        }
+    }
+}
+
+/// How many of `len` returns `drop_outliers` removes at `fraction`.
+pub fn outliers_dropped(len: usize, fraction: f64) -> usize {
+    (len as f64 * fraction).floor() as usize
+}
+
+/// `returns` without the `outliers_dropped` largest absolute values.
+fn drop_outliers(returns: &[f64], fraction: f64) -> Vec<f64> {
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+    sorted.truncate(returns.len() - outliers_dropped(returns.len(), fraction));
+    sorted
+}
+
+/// Linearly interpolated quantile of an ascending slice, as numpy's default.
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Distribution {
+    #[serde(serialize_with = "decimal")]
+    pub min: f64,
+    #[serde(serialize_with = "decimal")]
+    pub p25: f64,
+    #[serde(serialize_with = "decimal")]
+    pub median: f64,
+    #[serde(serialize_with = "decimal")]
+    pub p75: f64,
+    #[serde(serialize_with = "decimal")]
+    pub max: f64,
+}
+
+/// Quartiles and extremes of `values`, `None` when empty.
+pub fn distribution(values: &[f64]) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    Some(Distribution {
+        min: sorted[0],
+        p25: quantile(&sorted, 0.25),
+        median: quantile(&sorted, 0.5),
+        p75: quantile(&sorted, 0.75),
+        max: sorted[sorted.len() - 1],
+    })
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RegimeLabel {
+    Low,
+    Normal,
+    High,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Regime {
+    pub regime: RegimeLabel,
+    /// Share of `values` at or below the latest one.
+    #[serde(serialize_with = "decimal")]
+    pub percentile: f64,
+}
+
+/// Classifies the last of `values` against all of them: below the `low`
+/// percentile is low, above `high` is high. Non-finite values are ignored.
+pub fn classify_regime(values: &[f64], low: f64, high: f64) -> Option<Regime> {
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let latest = *finite.last()?;
+    let at_or_below = finite.iter().filter(|&&v| v <= latest).count();
+    let percentile = at_or_below as f64 / finite.len() as f64;
+    let regime = if percentile < low {
+        RegimeLabel::Low
+    } else if percentile > high {
+        RegimeLabel::High
+    } else {
+        RegimeLabel::Normal
+    };
+    Some(Regime { regime, percentile })
+}
+
+/// Clamps `values` to their `[p, 1 - p]` quantiles.
+pub fn winsorize(values: &mut [f64], p: f64) {
+    if values.is_empty() {
+        return;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let (low, high) = (quantile(&sorted, p), quantile(&sorted, 1.0 - p));
+    for value in values {
+        *value = value.clamp(low, high);
+    }
+}
+
+/// Sample (`bessel`) or population variance of log returns. With `bessel`
+/// this matches pandas `Series.var()` and `numpy.var(ddof=1)`; without it,
+/// `numpy.var()`'s default `ddof=0`.
+pub fn close_to_close_variance(returns: &[f64], bessel: bool) -> f64 {
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let sum_sq: f64 = returns.iter().map(|r| (r - mean).powi(2)).sum();
+    let dof = if bessel { n - 1.0 } else { n };
+    sum_sq / dof
+}
+
+/// Realized variance of a price path, averaged over the `stride` offset
+/// subgrids that sample every `stride`-th price. Sparser sampling dilutes
+/// microstructure noise (bid-ask bounce, discreteness) that inflates the sum
+/// of squared high-frequency returns, and averaging over every offset wins
+/// back most of the data the sparser grid throws away. A simplified form of
+/// the Zhang, Mykland and Aït-Sahalia subsampling estimator, without its
+/// bias-correction term. A `stride` of 1 is plain realized variance.
+pub fn subsampled_realized_variance(prices: &[f64], stride: usize) -> f64 {
+    let subgrids = stride.max(1);
+    let total: f64 = (0..subgrids)
+        .map(|offset| {
+            prices[offset..]
+                .iter()
+                .step_by(subgrids)
+                .collect::<Vec<_>>()
+                .windows(2)
+                .map(|pair| (pair[1] / pair[0]).ln().powi(2))
+                .sum::<f64>()
+        })
+        .sum();
+    total / subgrids as f64
+}
+
+/// Log returns grouped by the weekday of the candle they end on, Monday first.
+pub fn returns_by_weekday(klines: &[CandleData]) -> [Vec<f64>; 7] {
+    let mut buckets: [Vec<f64>; 7] = Default::default();
+    for pair in klines.windows(2) {
+        let weekday = pair[1].start.weekday().num_days_from_monday() as usize;
+        buckets[weekday].push((pair[1].close / pair[0].close).ln());
+    }
+    buckets
+}
+
+/// Exponentially weighted variance of log returns, the newest return carrying
+/// weight 1 and each older one `lambda` times the next. With `bessel` the
+/// weighted bias correction is applied, matching pandas
+/// `ewm(alpha=1 - lambda).var()`; without it, `var(bias=True)`.
+pub fn ewma_variance(returns: &[f64], lambda: f64, bessel: bool) -> f64 {
+    let n = returns.len();
+    let weights: Vec<f64> = (0..n).map(|i| lambda.powi((n - 1 - i) as i32)).collect();
+    let v1: f64 = weights.iter().sum();
+    let mean = weights.iter().zip(returns).map(|(w, r)| w * r).sum::<f64>() / v1;
+    let biased = weights
+        .iter()
+        .zip(returns)
+        .map(|(w, r)| w * (r - mean).powi(2))
+        .sum::<f64>()
+        / v1;
+    if !bessel {
+        return biased;
+    }
+    let v2: f64 = weights.iter().map(|w| w * w).sum();
+    biased * v1 * v1 / (v1 * v1 - v2)
+}
+
+/// Per-period variance of every `window`-candle window, oldest first, with the
+/// same alignment as `rolling_parkinson`. Return-based estimators see the
+/// `window - 1` log returns between the window's candles, and give no points
+/// when that leaves nothing to divide by.
+pub fn rolling_variance(
+    estimator: Estimator,
+    klines: &[CandleData],
+    window: usize,
+    options: &EstimatorOptions,
+) -> Vec<f64> {
+    let range_based = |term: fn(&CandleData) -> f64| {
+        let terms: Vec<f64> = klines.iter().map(term).collect();
+        rolling_mean(&terms, window)
+            .into_iter()
+            // Garman-Klass terms can be negative, as can float drift around zero.
+            .map(|variance| variance.max(0.0))
+            .collect()
+    };
+    match estimator {
+        Estimator::Parkinson => return rolling_parkinson(klines, window),
+        Estimator::GarmanKlass => return range_based(garman_klass_term),
+        Estimator::RogersSatchell => return range_based(rogers_satchell_term),
+        Estimator::CloseToClose | Estimator::Ewma => {}
+    }
+    // Bessel's correction of a single return divides by zero.
+    let min_window = if options.bessel { 3 } else { 2 };
+    if window < min_window || klines.len() < window {
+        return Vec::new();
+    }
+    log_returns(klines)
+        .windows(window - 1)
+        .map(|returns| {
+            let mut returns = returns.to_vec();
+            if let Some(p) = options.winsorize {
+                winsorize(&mut returns, p);
+            }
+            match estimator {
+                Estimator::CloseToClose => match options.drop_outliers {
+                    Some(fraction) => {
+                        close_to_close_variance(&drop_outliers(&returns, fraction), options.bessel)
+                    }
+                    None => close_to_close_variance(&returns, options.bessel),
+                },
+                Estimator::Ewma => ewma_variance(&returns, EWMA_LAMBDA, options.bessel),
+                _ => unreachable!(),
+            }
+        })
+        .collect()
+}
+
+pub fn log_returns(klines: &[CandleData]) -> Vec<f64> {
+    klines
+        .windows(2)
+        .map(|pair| (pair[1].close / pair[0].close).ln())
+        .collect()
+}
+
+/// Simple arithmetic returns `close_t / close_{t-1} - 1`. Unlike log returns
+/// these don't add up across periods and aren't symmetric: +10% then -10%
+/// leaves you at 0.99, while log returns of `ln(1.1)` and `-ln(1.1)` net to 0.
+pub fn pct_changes(klines: &[CandleData]) -> Vec<f64> {
+    klines
+        .windows(2)
+        .map(|pair| pair[1].close / pair[0].close - 1.0)
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub bin_start: f64,
+    pub bin_end: f64,
+    pub count: usize,
+}
+
+/// Buckets `values` into `bins` equal-width bins spanning min..=max, the last
+/// bin being closed on the right. If every value is equal a single zero-width
+/// bin holds them all.
+pub fn histogram(values: &[f64], bins: usize) -> Vec<HistogramBin> {
+    if values.is_empty() || bins == 0 {
+        return Vec::new();
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if min == max {
+        return vec![HistogramBin {
+            bin_start: min,
+            bin_end: max,
+            count: values.len(),
+        }];
+    }
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for value in values {
+        let index = (((value - min) / width) as usize).min(bins - 1);
+        counts[index] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| HistogramBin {
+            bin_start: min + width * i as f64,
+            bin_end: min + width * (i + 1) as f64,
+            count,
+        })
+        .collect()
+}
+
+/// Pairs of candles from `a` and `b` sharing a `start`. Both inputs must be
+/// sorted oldest first.
+pub fn align_candles<'a>(
+    a: &'a [CandleData],
+    b: &'a [CandleData],
+) -> Vec<(&'a CandleData, &'a CandleData)> {
+    let (mut i, mut j) = (0, 0);
+    let mut aligned = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].start.cmp(&b[j].start) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                aligned.push((&a[i], &b[j]));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    aligned
+}
+
+/// Log returns of both legs of an aligned series, labeled by the later
+/// candle's `start`.
+pub fn aligned_log_returns(
+    aligned: &[(&CandleData, &CandleData)],
+) -> Vec<(DateTime<Utc>, f64, f64)> {
+    aligned
+        .windows(2)
+        .map(|pair| {
+            let (prev_a, prev_b) = pair[0];
+            let (a, b) = pair[1];
+            (
+                a.start,
+                (a.close / prev_a.close).ln(),
+                (b.close / prev_b.close).ln(),
+            )
+        })
+        .collect()
+}
+
+/// Pearson correlation of `a` and `b`, NaN if either is constant.
+pub fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len()) as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Beta {
+    pub beta: f64,
+    pub r_squared: f64,
+    pub sample_size: usize,
+}
+
+/// OLS beta of `target` on `benchmark` returns, `cov / var(benchmark)`, with
+/// R² as the squared correlation. `None` for fewer than two samples or a
+/// constant benchmark.
+pub fn calculate_beta(target: &[f64], benchmark: &[f64]) -> Option<Beta> {
+    let n = target.len().min(benchmark.len());
+    if n < 2 {
+        return None;
+    }
+    let (target, benchmark) = (&target[..n], &benchmark[..n]);
+    let mean_t = target.iter().sum::<f64>() / n as f64;
+    let mean_b = benchmark.iter().sum::<f64>() / n as f64;
+    let (mut cov, mut var_t, mut var_b) = (0.0, 0.0, 0.0);
+    for (t, b) in target.iter().zip(benchmark) {
+        cov += (t - mean_t) * (b - mean_b);
+        var_t += (t - mean_t).powi(2);
+        var_b += (b - mean_b).powi(2);
+    }
+    if var_b == 0.0 {
+        return None;
+    }
+    let r_squared = if var_t == 0.0 {
+        0.0
+    } else {
+        cov * cov / (var_t * var_b)
+    };
+    Some(Beta {
+        beta: cov / var_b,
+        r_squared,
+        sample_size: n,
+    })
+}
+
+/// Smallest R/S chunk; shorter chunks make the statistic too noisy.
+const MIN_HURST_CHUNK: usize = 8;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Hurst {
+    /// Above 0.5 the series trends (persistent), below 0.5 it mean-reverts,
+    /// around 0.5 it behaves like a random walk.
+    pub hurst: f64,
+    /// Fit quality of the log-log regression.
+    pub r_squared: f64,
+    pub chunk_sizes: Vec<usize>,
+}
+
+/// Mean rescaled range of `returns` split into `size`-long chunks.
+fn rescaled_range(returns: &[f64], size: usize) -> Option<f64> {
+    let ratios: Vec<f64> = returns
+        .chunks_exact(size)
+        .filter_map(|chunk| {
+            let mean = chunk.iter().sum::<f64>() / size as f64;
+            let (mut cumulative, mut min, mut max) = (0.0_f64, 0.0_f64, 0.0_f64);
+            for r in chunk {
+                cumulative += r - mean;
+                min = min.min(cumulative);
+                max = max.max(cumulative);
+            }
+            let std = (chunk.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / size as f64).sqrt();
+            (std > 0.0).then(|| (max - min) / std)
+        })
+        .collect();
+    (!ratios.is_empty()).then(|| ratios.iter().sum::<f64>() / ratios.len() as f64)
+}
+
+/// Least-squares slope and R² of `y` on `x`.
+fn linear_regression(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (xi, yi) in x.iter().zip(y) {
+        sxy += (xi - mean_x) * (yi - mean_y);
+        sxx += (xi - mean_x).powi(2);
+        syy += (yi - mean_y).powi(2);
+    }
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+    (sxy / sxx, r_squared)
+}
+
+/// Hurst exponent of a log-price series from its log returns, by rescaled
+/// range analysis: R/S is averaged over chunks of 8, 16, 32, ... returns (up
+/// to half the series) and H is the slope of `ln(R/S)` against `ln(size)`.
+/// `None` when fewer than two chunk sizes fit.
+pub fn hurst_exponent(returns: &[f64]) -> Option<Hurst> {
+    let mut sizes = Vec::new();
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    let mut size = MIN_HURST_CHUNK;
+    while size <= returns.len() / 2 {
+        if let Some(rs) = rescaled_range(returns, size) {
+            sizes.push(size);
+            xs.push((size as f64).ln());
+            ys.push(rs.ln());
+        }
+        size *= 2;
+    }
+    if sizes.len() < 2 {
+        return None;
+    }
+    let (hurst, r_squared) = linear_regression(&xs, &ys);
+    Some(Hurst {
+        hurst,
+        r_squared,
+        chunk_sizes: sizes,
+    })
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Drawdown {
+    /// Largest peak-to-trough decline of the close, as a fraction of the peak.
+    pub max_drawdown: f64,
+    pub peak_at: DateTime<Utc>,
+    pub trough_at: DateTime<Utc>,
+    /// First close back at or above the peak, `None` if it never recovered.
+    pub recovery_at: Option<DateTime<Utc>>,
+}
+
+pub fn max_drawdown(klines: &[CandleData]) -> Option<Drawdown> {
+    let first = klines.first()?;
+    let (mut peak, mut worst_peak, mut worst_trough) = (first, first, first);
+    let mut worst = 0.0;
+    for kline in klines {
+        if kline.close > peak.close {
+            peak = kline;
+        }
+        let drawdown = 1.0 - kline.close / peak.close;
+        if drawdown > worst {
+            worst = drawdown;
+            worst_peak = peak;
+            worst_trough = kline;
+        }
+    }
+    let recovery_at = if worst > 0.0 {
+        klines
+            .iter()
+            .skip_while(|k| k.start <= worst_trough.start)
+            .find(|k| k.close >= worst_peak.close)
+            .map(|k| k.start)
+    } else {
+        None
+    };
+    Some(Drawdown {
+        max_drawdown: worst,
+        peak_at: worst_peak.start,
+        trough_at: worst_trough.start,
+        recovery_at,
+    })
+}
+
+/// Annualized mean log return over annualized Parkinson volatility. The mean
+/// scales by `periods_per_year` and the volatility by its square root, so the
+/// ratio scales by `sqrt(periods_per_year)`. `None` when the window has zero
+/// volatility.
+pub fn calculate_sharpe(klines: &[CandleData], periods_per_year: f64) -> Option<f64> {
+    let returns = log_returns(klines);
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let vol = annualize(calculate_parkinson(klines), periods_per_year);
+    if vol == 0.0 {
+        return None;
+    }
+    Some(mean * periods_per_year / vol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(start_millis: i64, high: f64, low: f64) -> CandleData {
+        CandleData {
+            start: DateTime::from_timestamp_millis(start_millis).unwrap(),
+            open: low,
+            high,
+            low,
+            close: high,
+            volume: "0".to_string(),
+            turnover: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn rolling_parkinson_matches_naive_windows() {
+        let candles: Vec<CandleData> = (0..200)
+            .map(|i| {
+                let mid = 100.0 + (i as f64 / 7.0).sin() * 10.0;
+                let spread = 1.0 + (i % 11) as f64 * 0.3;
+                candle(i, mid + spread, mid - spread)
+            })
+            .collect();
+
+        for window in [1, 7, 30, 200] {
+            let rolling = rolling_parkinson(&candles, window);
+            let naive: Vec<f64> = candles.windows(window).map(calculate_parkinson).collect();
+            assert_eq!(rolling.len(), naive.len());
+            for (fast, slow) in rolling.iter().zip(&naive) {
+                assert!((fast - slow).abs() < 1e-12, "{fast} != {slow}");
+            }
+        }
+        assert!(rolling_parkinson(&candles, 201).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{ser, Serialize, Serializer};
+use serde_json::value::RawValue;
+
+/// An `f64` that serializes in plain decimal notation (`0.00003`, never
+/// `3e-5`), with non-finite values as `null`. Only meaningful with
+/// `serde_json`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decimal(pub f64);
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.0.is_finite() {
+            return serializer.serialize_none();
+        }
+        // `Display` for f64 never uses an exponent and round-trips exactly.
+        let raw = RawValue::from_string(self.0.to_string()).map_err(ser::Error::custom)?;
+        raw.serialize(serializer)
+    }
+}
+
+/// For `#[serde(serialize_with)]` on plain `f64` fields.
+pub fn decimal<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    Decimal(*value).serialize(serializer)
+}
+
+/// For `#[serde(serialize_with)]` on `Option<f64>` fields.
+pub fn optional_decimal<S: Serializer>(
+    value: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.map(Decimal).serialize(serializer)
+}
+
+/// Field separators accepted for CSV output. `;` suits spreadsheets in
+/// locales where `,` is the decimal separator.
+pub const CSV_DELIMITERS: [char; 3] = [',', ';', '\t'];
+
+/// A two-column CSV document with a header row. Numbers are written in plain
+/// decimal with a `.` whatever the reader's locale, and non-finite values as
+/// empty fields. Labels must not contain `delimiter`.
+pub fn csv(
+    header: [&str; 2],
+    rows: impl IntoIterator<Item = (String, f64)>,
+    delimiter: char,
+) -> String {
+    let mut out = format!("{}{delimiter}{}\r\n", header[0], header[1]);
+    for (label, value) in rows {
+        out.push_str(&label);
+        out.push(delimiter);
+        if value.is_finite() {
+            out.push_str(&value.to_string());
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_serialize_without_exponent() {
+        assert_eq!(serde_json::to_string(&Decimal(0.00003)).unwrap(), "0.00003");
+        assert_eq!(
+            serde_json::to_string(&vec![(1_i64, Decimal(1.5e-7))]).unwrap(),
+            "[[1,0.00000015]]"
+        );
+    }
+
+    #[test]
+    fn csv_is_parseable_with_each_delimiter() {
+        let rows = vec![
+            ("2024-06-01".to_string(), 0.5),
+            ("2024-06-02".to_string(), 0.0000125),
+            ("2024-06-03".to_string(), f64::NAN),
+        ];
+        for delimiter in CSV_DELIMITERS {
+            let out = csv(["time", "volatility"], rows.clone(), delimiter);
+            let parsed: Vec<Vec<&str>> = out
+                .lines()
+                .map(|line| line.split(delimiter).collect())
+                .collect();
+
+            assert_eq!(parsed[0], ["time", "volatility"], "{delimiter:?}");
+            assert_eq!(parsed.len(), rows.len() + 1);
+            for (fields, (label, value)) in parsed[1..].iter().zip(&rows) {
+                assert_eq!(fields.len(), 2, "{delimiter:?}");
+                assert_eq!(fields[0], label);
+                if value.is_finite() {
+                    assert_eq!(fields[1].parse::<f64>().unwrap(), *value);
+                } else {
+                    assert_eq!(fields[1], "");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_values_serialize_as_null() {
+        assert_eq!(serde_json::to_string(&Decimal(f64::NAN)).unwrap(), "null");
+        assert_eq!(
+            serde_json::to_string(&Decimal(f64::INFINITY)).unwrap(),
+            "null"
+        );
+    }
+}
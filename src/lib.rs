@@ -0,0 +1,4 @@
+pub mod estimators;
+pub mod format;
+pub mod providers;
+pub mod simulation;
@@ -0,0 +1,523 @@
+use std::fmt;
+
+use chrono::{serde::ts_milliseconds, DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitResponse<T> {
+    ret_code: i32,
+    ret_msg: String,
+    result: BybitList<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitList<T> {
+    // Absent when `retCode` is non-zero.
+    #[serde(default = "Vec::new")]
+    list: Vec<T>,
+}
+
+/// Bybit sends numbers as strings; user-supplied candles may use either.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber<T> {
+    String(String),
+    Number(T),
+}
+
+fn parse_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match StringOrNumber::<f64>::deserialize(deserializer)? {
+        StringOrNumber::String(s) => s.parse::<f64>().map_err(de::Error::custom),
+        StringOrNumber::Number(n) => Ok(n),
+    }
+}
+fn parse_datetime<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp_num = match StringOrNumber::<i64>::deserialize(deserializer)? {
+        StringOrNumber::String(s) => s.parse::<i64>().map_err(de::Error::custom)?,
+        StringOrNumber::Number(n) => n,
+    };
+    chrono::DateTime::from_timestamp_millis(timestamp_num)
+        .ok_or_else(|| de::Error::custom(format!("timestamp {timestamp_num} is out of range")))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CandleData {
+    #[serde(
+        deserialize_with = "parse_datetime",
+        serialize_with = "ts_milliseconds::serialize"
+    )]
+    pub start: DateTime<Utc>,
+    #[serde(deserialize_with = "parse_f64")]
+    pub open: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub high: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub low: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub close: f64,
+    #[serde(default)]
+    pub volume: String,
+    #[serde(default)]
+    pub turnover: String,
+}
+
+impl CandleData {
+    /// Why this candle can't be used by the estimators, if anything.
+    pub fn problem(&self) -> Option<&'static str> {
+        let prices = [self.open, self.high, self.low, self.close];
+        if prices.iter().any(|p| !p.is_finite() || *p <= 0.0) {
+            Some("prices must be positive")
+        } else if self.high < self.low
+            || self.high < self.open.max(self.close)
+            || self.low > self.open.min(self.close)
+        {
+            Some("high and low must bound open and close")
+        } else {
+            None
+        }
+    }
+}
+
+/// Length of one candle of `interval`, or `None` for monthly candles, whose
+/// length varies, and for anything that isn't a positive number of minutes.
+pub fn interval_duration(interval: &str) -> Option<Duration> {
+    match interval {
+        "D" => Some(Duration::days(1)),
+        "W" => Some(Duration::weeks(1)),
+        "M" => None,
+        minutes => minutes
+            .parse()
+            .ok()
+            .filter(|&minutes: &i64| minutes > 0)
+            .and_then(Duration::try_minutes),
+    }
+}
+
+/// Bybit's spelling of a symbol: `btc/usdt`, `BTC-USDT` and `btc_usdt` all
+/// become `BTCUSDT`.
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol
+        .chars()
+        .filter(|c| !matches!(c, '/' | '-' | '_' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+const BYBIT_URL: &str = "https://api.bybit.com/v5/market";
+/// Bybit's per-request kline cap.
+pub const MAX_LIMIT: usize = 1000;
+/// Bybit's per-request open interest cap.
+pub const MAX_OPEN_INTEREST_LIMIT: usize = 200;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenInterest {
+    #[serde(
+        deserialize_with = "parse_datetime",
+        serialize_with = "ts_milliseconds::serialize"
+    )]
+    pub timestamp: DateTime<Utc>,
+    #[serde(deserialize_with = "parse_f64")]
+    pub open_interest: f64,
+}
+
+#[derive(Debug)]
+pub enum ProviderError {
+    Http(reqwest::Error),
+    Decode(serde_json::Error),
+    Exchange {
+        code: i32,
+        message: String,
+    },
+    Unsupported(&'static str),
+    /// More than `MAX_HISTORY` candles separate this time from now.
+    HistoryTooLong(DateTime<Utc>),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::Http(err) => write!(f, "request to Bybit failed: {err}"),
+            ProviderError::Decode(err) => write!(f, "unexpected Bybit response: {err}"),
+            ProviderError::Exchange { code, message } => write!(f, "Bybit error {code}: {message}"),
+            ProviderError::Unsupported(what) => {
+                write!(f, "{what} is not available for this interval")
+            }
+            ProviderError::HistoryTooLong(since) => {
+                write!(f, "more than {MAX_HISTORY} candles since {since}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<reqwest::Error> for ProviderError {
+    fn from(err: reqwest::Error) -> Self {
+        ProviderError::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for ProviderError {
+    fn from(err: serde_json::Error) -> Self {
+        ProviderError::Decode(err)
+    }
+}
+
+/// The most recent `limit` candles, or the most recent ones starting at or
+/// before `end` when it is set. Bybit defaults to the linear (USDT perpetual)
+/// category.
+pub async fn fetch_ohlc(
+    client: &Client,
+    symbol: &str,
+    interval: &str,
+    limit: usize,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<CandleData>, ProviderError> {
+    let mut query = vec![
+        ("symbol", normalize_symbol(symbol)),
+        ("interval", interval.to_string()),
+        ("limit", limit.to_string()),
+    ];
+    if let Some(end) = end {
+        query.push(("end", end.timestamp_millis().to_string()));
+    }
+
+    let url = format!("{BYBIT_URL}/kline");
+    Ok(prepare_candles(fetch_list(client, &url, &query).await?))
+}
+
+/// Safety cap for `fetch_ohlc_history`: ten years of daily candles.
+pub const MAX_HISTORY: usize = 3653;
+
+/// Every candle Bybit has up to `end` (or now), paging backward `MAX_LIMIT` at a
+/// time until a page comes back short or `max_candles` is reached.
+pub async fn fetch_ohlc_history(
+    client: &Client,
+    symbol: &str,
+    interval: &str,
+    max_candles: usize,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<CandleData>, ProviderError> {
+    let mut candles: Vec<CandleData> = Vec::new();
+    let mut end = end;
+    while candles.len() < max_candles {
+        let limit = MAX_LIMIT.min(max_candles - candles.len());
+        let page = fetch_ohlc(client, symbol, interval, limit, end).await?;
+        let Some(first) = page.first() else {
+            break;
+        };
+        end = Some(first.start - Duration::milliseconds(1));
+        let short = page.len() < limit;
+        candles.extend(page);
+        if short {
+            break;
+        }
+    }
+    Ok(prepare_candles(candles))
+}
+
+/// Candles starting strictly after `since`, oldest first, for incremental
+/// updates. Bybit returns the newest candles of a range first, so a backlog
+/// longer than `MAX_LIMIT` is paged backward from now until it reaches
+/// `since`. A backlog of more than `MAX_HISTORY` candles is an error rather
+/// than a series with a hole after `since`.
+pub async fn fetch_ohlc_since(
+    client: &Client,
+    symbol: &str,
+    interval: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<CandleData>, ProviderError> {
+    fetch_ohlc_since_from(client, BYBIT_URL, symbol, interval, since).await
+}
+
+async fn fetch_ohlc_since_from(
+    client: &Client,
+    base_url: &str,
+    symbol: &str,
+    interval: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<CandleData>, ProviderError> {
+    let start = since + Duration::milliseconds(1);
+    let mut candles: Vec<CandleData> = Vec::new();
+    let mut end: Option<DateTime<Utc>> = None;
+    loop {
+        if candles.len() >= MAX_HISTORY {
+            return Err(ProviderError::HistoryTooLong(since));
+        }
+        let mut query = vec![
+            ("symbol", normalize_symbol(symbol)),
+            ("interval", interval.to_string()),
+            ("limit", MAX_LIMIT.to_string()),
+            ("start", start.timestamp_millis().to_string()),
+        ];
+        if let Some(end) = end {
+            query.push(("end", end.timestamp_millis().to_string()));
+        }
+        let url = format!("{base_url}/kline");
+        let page = prepare_candles(fetch_list(client, &url, &query).await?);
+        let Some(first) = page.first() else {
+            break;
+        };
+        end = Some(first.start - Duration::milliseconds(1));
+        let short = page.len() < MAX_LIMIT;
+        candles.extend(page);
+        if short {
+            break;
+        }
+    }
+    candles.retain(|candle| candle.start > since);
+    Ok(prepare_candles(candles))
+}
+
+/// Bybit's open interest period for a kline interval, where one exists.
+pub fn open_interest_interval(interval: &str) -> Option<&'static str> {
+    match interval {
+        "5" => Some("5min"),
+        "15" => Some("15min"),
+        "30" => Some("30min"),
+        "60" => Some("1h"),
+        "240" => Some("4h"),
+        "D" => Some("1d"),
+        _ => None,
+    }
+}
+
+/// Open interest of a linear perpetual, oldest first. `interval` is a kline
+/// interval accepted by `open_interest_interval`; `limit` is capped at
+/// `MAX_OPEN_INTEREST_LIMIT`.
+pub async fn fetch_open_interest(
+    client: &Client,
+    symbol: &str,
+    interval: &str,
+    limit: usize,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<OpenInterest>, ProviderError> {
+    let interval_time =
+        open_interest_interval(interval).ok_or(ProviderError::Unsupported("open interest"))?;
+    let mut query = vec![
+        ("category", "linear".to_string()),
+        ("symbol", normalize_symbol(symbol)),
+        ("intervalTime", interval_time.to_string()),
+        ("limit", limit.min(MAX_OPEN_INTEREST_LIMIT).to_string()),
+    ];
+    if let Some(end) = end {
+        query.push(("endTime", end.timestamp_millis().to_string()));
+    }
+
+    let url = format!("{BYBIT_URL}/open-interest");
+    let mut list: Vec<OpenInterest> = fetch_list(client, &url, &query).await?;
+    list.sort_by_key(|oi| oi.timestamp);
+    Ok(list)
+}
+
+/// GETs `url` with `query` percent-encoded, so no parameter can add to or
+/// rewrite the others.
+async fn fetch_list<T: DeserializeOwned>(
+    client: &Client,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<Vec<T>, ProviderError> {
+    let body = client.get(url).query(query).send().await?.text().await?;
+    let response = serde_json::from_str::<BybitResponse<T>>(body.as_str())?;
+
+    if response.ret_code != 0 {
+        return Err(ProviderError::Exchange {
+            code: response.ret_code,
+            message: response.ret_msg,
+        });
+    }
+
+    Ok(response.result.list)
+}
+
+/// Sorts oldest first (Bybit returns newest first) and collapses candles
+/// sharing a `start`, keeping the last one received.
+pub fn prepare_candles(mut candles: Vec<CandleData>) -> Vec<CandleData> {
+    candles.sort_by_key(|c| c.start);
+    let before = candles.len();
+    candles.dedup_by(|later, kept| {
+        if later.start == kept.start {
+            std::mem::swap(later, kept);
+            true
+        } else {
+            false
+        }
+    });
+    let removed = before - candles.len();
+    if removed > 0 {
+        tracing::warn!(removed, "dropped candles with duplicate timestamps");
+    }
+    candles
+}
+
+/// Aggregates sorted candles into bars `factor` candles long. Bars start on
+/// multiples of their own length since the epoch (plus four days for weekly
+/// bars, which start on Monday like Bybit's). Each bar takes the first open,
+/// the highest high, the lowest low, the last close and the summed volume and
+/// turnover. Bars missing any of their candles, such as the partial bars at
+/// either end, are dropped so every bar covers the full period.
+pub fn resample(candles: &[CandleData], to: Duration, factor: usize) -> Vec<CandleData> {
+    let offset = if to == Duration::weeks(1) {
+        Duration::days(4)
+    } else {
+        Duration::zero()
+    };
+    let bucket = |candle: &CandleData| {
+        (candle.start.timestamp_millis() - offset.num_milliseconds())
+            .div_euclid(to.num_milliseconds())
+    };
+    let sum = |bar: &[CandleData], field: fn(&CandleData) -> &str| {
+        bar.iter()
+            .map(|c| field(c).parse::<f64>().unwrap_or(0.0))
+            .sum::<f64>()
+            .to_string()
+    };
+    candles
+        .chunk_by(|a, b| bucket(a) == bucket(b))
+        .filter(|bar| bar.len() == factor)
+        .map(|bar| CandleData {
+            start: DateTime::from_timestamp_millis(
+                bucket(&bar[0]) * to.num_milliseconds() + offset.num_milliseconds(),
+            )
+            .expect("bar start is in range"),
+            open: bar[0].open,
+            high: bar.iter().map(|c| c.high).fold(f64::NEG_INFINITY, f64::max),
+            low: bar.iter().map(|c| c.low).fold(f64::INFINITY, f64::min),
+            close: bar[bar.len() - 1].close,
+            volume: sum(bar, |c| &c.volume),
+            turnover: sum(bar, |c| &c.turnover),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+
+    use super::*;
+
+    /// Newest candle the fake exchange has.
+    const NOW_MILLIS: i64 = 10_000 * 60_000;
+
+    /// Serves minute candles from time zero to `NOW_MILLIS` the way Bybit's
+    /// kline endpoint pages them: the newest `limit` in `[start, end]`, newest
+    /// first. Returns the base URL.
+    async fn fake_bybit() -> String {
+        async fn kline(Query(params): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+            let param = |name: &str| params.get(name).map(|v| v.parse::<i64>().unwrap());
+            let start = param("start").unwrap_or(0);
+            let end = param("end").unwrap_or(NOW_MILLIS).min(NOW_MILLIS);
+            let limit = param("limit").unwrap() as usize;
+            let list: Vec<serde_json::Value> = (0..=end / 60_000)
+                .rev()
+                .map(|minute| minute * 60_000)
+                .take_while(|&at| at >= start)
+                .take(limit)
+                .map(|at| serde_json::json!([at.to_string(), "1", "1", "1", "1", "0", "0"]))
+                .collect();
+            Json(serde_json::json!({ "retCode": 0, "retMsg": "OK", "result": { "list": list } }))
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/kline", get(kline));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn fetch_ohlc_since_pages_back_to_since() {
+        let base_url = fake_bybit().await;
+        let client = Client::builder().no_proxy().build().unwrap();
+        let since = DateTime::from_timestamp_millis(NOW_MILLIS - 2_500 * 60_000).unwrap();
+
+        let candles = fetch_ohlc_since_from(&client, &base_url, "BTCUSDT", "1", since)
+            .await
+            .unwrap();
+
+        assert_eq!(candles.len(), 2_500);
+        assert_eq!(candles[0].start, since + Duration::minutes(1));
+        assert_eq!(candles[2_499].start.timestamp_millis(), NOW_MILLIS);
+    }
+
+    #[tokio::test]
+    async fn fetch_ohlc_since_rejects_a_backlog_beyond_max_history() {
+        let base_url = fake_bybit().await;
+        let client = Client::builder().no_proxy().build().unwrap();
+        let since = DateTime::from_timestamp_millis(0).unwrap();
+
+        let result = fetch_ohlc_since_from(&client, &base_url, "BTCUSDT", "1", since).await;
+
+        assert!(matches!(result, Err(ProviderError::HistoryTooLong(_))));
+    }
+
+    fn candle(start_millis: i64, close: f64) -> CandleData {
+        CandleData {
+            start: DateTime::from_timestamp_millis(start_millis).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: "0".to_string(),
+            turnover: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn normalize_symbol_accepts_common_spellings() {
+        for spelling in [
+            "BTCUSDT", "btcusdt", "BTC/USDT", "btc-usdt", "Btc_Usdt", "BTC USDT",
+        ] {
+            assert_eq!(normalize_symbol(spelling), "BTCUSDT", "{spelling:?}");
+        }
+    }
+
+    #[test]
+    fn resample_drops_partial_bars_at_both_ends() {
+        // 32 hours of minute candles starting 17 minutes into an hour, the
+        // most a request for 30 hourly bars fetches.
+        let candles: Vec<CandleData> = (0..32 * 60)
+            .map(|i| candle((17 + i) * 60_000, 1.0))
+            .collect();
+
+        let bars = resample(&candles, Duration::hours(1), 60);
+
+        assert_eq!(bars.len(), 31);
+        assert_eq!(bars[0].start.timestamp_millis(), 60 * 60_000);
+        assert!(bars
+            .windows(2)
+            .all(|pair| pair[1].start - pair[0].start == Duration::hours(1)));
+    }
+
+    #[test]
+    fn prepare_candles_drops_duplicate_timestamps() {
+        let candles = vec![
+            candle(3_000, 3.0),
+            candle(2_000, 2.0),
+            candle(2_000, 2.5),
+            candle(1_000, 1.0),
+        ];
+
+        let prepared = prepare_candles(candles);
+
+        assert_eq!(prepared.len(), 3);
+        assert!(prepared
+            .windows(2)
+            .all(|pair| pair[0].start < pair[1].start));
+        assert_eq!(prepared[1].close, 2.5);
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::providers::CandleData;
+
+/// Candles of a driftless geometric Brownian motion with annualized
+/// volatility `sigma`, each built from `steps` intra-candle price samples so
+/// high and low come from the simulated path rather than the endpoints.
+pub fn gbm_candles<R: Rng>(
+    rng: &mut R,
+    sigma: f64,
+    periods_per_year: f64,
+    count: usize,
+    steps: usize,
+) -> Vec<CandleData> {
+    let dt = 1.0 / (periods_per_year * steps as f64);
+    let step_sigma = sigma * dt.sqrt();
+    let drift = -0.5 * sigma * sigma * dt;
+    let period = Duration::milliseconds((365.25 * 86_400_000.0 / periods_per_year) as i64);
+    let mut start = DateTime::<Utc>::UNIX_EPOCH;
+    let mut log_price = 100.0_f64.ln();
+
+    (0..count)
+        .map(|_| {
+            let open = log_price;
+            let (mut high, mut low) = (open, open);
+            for _ in 0..steps {
+                let shock: f64 = rng.sample(StandardNormal);
+                log_price += drift + step_sigma * shock;
+                high = high.max(log_price);
+                low = low.min(log_price);
+            }
+            let candle = CandleData {
+                start,
+                open: open.exp(),
+                high: high.exp(),
+                low: low.exp(),
+                close: log_price.exp(),
+                volume: "0".to_string(),
+                turnover: "0".to_string(),
+            };
+            start += period;
+            candle
+        })
+        .collect()
+}